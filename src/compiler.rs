@@ -1,4 +1,15 @@
-use std::ops::Add;
+// The byte-oriented compiler below lives at `crate::compiler`. The older
+// word-width implementation used by the bytecode `Interpreter` lives in the
+// submodules, reached as `crate::compiler::{chunk, op_code, compiler}`; the two
+// tracks are namespaced rather than merged so each can evolve independently.
+pub mod chunk;
+pub mod compiler;
+pub mod op_code;
+
+use std::rc::Rc;
+
+use num_complex::Complex64;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     lexer::{Lexer, LexerError, OpToken, Token, TokenClass, TokenMeta},
@@ -20,10 +31,63 @@ pub enum CompilerError {
     Lexer(LexerError),
     Parser(ParserError),
     Compiler(CompileError),
+    Io(std::io::Error),
+}
+
+impl CompilerError {
+    /// Renders the failure against the original `source`, underlining the
+    /// offending text with caret markers when the error carries a location.
+    pub fn render(&self, source: &str) -> String {
+        let (span, message) = self.locate();
+        match span {
+            Some(span) => span.underline(source, &message),
+            None => format!("error: {message}\n"),
+        }
+    }
+
+    /// Extracts the offending span (when known) and a human-readable message.
+    fn locate(&self) -> (Option<Span>, String) {
+        match self {
+            CompilerError::Lexer(err) => {
+                (Some(Span::from_meta(lexer_meta(err))), format!("{err:?}"))
+            }
+            CompilerError::Parser(err) => {
+                let span = parser_token(err).map(|token| Span::from_meta(&token.meta));
+                (span, format!("{err:?}"))
+            }
+            CompilerError::Compiler(err) => (None, format!("{err:?}")),
+            CompilerError::Io(err) => (None, err.to_string()),
+        }
+    }
+}
+
+/// Returns the span metadata a lexer error points at.
+fn lexer_meta(error: &LexerError) -> &TokenMeta {
+    match error {
+        LexerError::UnexpectedEndOfFile { meta }
+        | LexerError::UnexpectedCharacter { meta, .. }
+        | LexerError::InvalidNumericLit { meta, .. }
+        | LexerError::UnterminatedString { meta }
+        | LexerError::MalformedEscape { meta, .. } => meta,
+    }
+}
+
+/// Returns the token a parser error points at, when it carries one.
+fn parser_token(error: &ParserError) -> Option<&Token> {
+    match error {
+        ParserError::UnexpectedEndOfTokenStream => None,
+        ParserError::ExpectedEoF { token }
+        | ParserError::ExpectedExpression { token }
+        | ParserError::ExpectedOpToken { token }
+        | ParserError::UnclosedExpression { token }
+        | ParserError::UnexpectedToken { token, .. }
+        | ParserError::UnexpectedUnaryOperator { token }
+        | ParserError::UnhandledToken { token } => Some(token),
+    }
 }
 
 #[derive(Clone, Copy)]
-#[repr(usize)]
+#[repr(u8)]
 pub enum OpCode {
     Constant = 0,
     Add = 1,
@@ -31,10 +95,27 @@ pub enum OpCode {
     Multiply = 3,
     Divide = 4,
     Negate = 5,
+    Not = 6,
+    Equal = 7,
+    Greater = 8,
+    Less = 9,
+    And = 10,
+    Or = 11,
+    True = 12,
+    False = 13,
+    Nil = 14,
+    JumpIfFalse = 15,
+    Jump = 16,
+    Loop = 17,
+    DefineGlobal = 18,
+    GetGlobal = 19,
+    SetGlobal = 20,
+    ConstantLong = 21,
+    Pop = 22,
 }
 
 impl OpCode {
-    pub fn from_usize(byte: usize) -> Option<OpCode> {
+    pub fn from_u8(byte: u8) -> Option<OpCode> {
         match byte {
             0 => Some(OpCode::Constant),
             1 => Some(OpCode::Add),
@@ -42,23 +123,158 @@ impl OpCode {
             3 => Some(OpCode::Multiply),
             4 => Some(OpCode::Divide),
             5 => Some(OpCode::Negate),
+            6 => Some(OpCode::Not),
+            7 => Some(OpCode::Equal),
+            8 => Some(OpCode::Greater),
+            9 => Some(OpCode::Less),
+            10 => Some(OpCode::And),
+            11 => Some(OpCode::Or),
+            12 => Some(OpCode::True),
+            13 => Some(OpCode::False),
+            14 => Some(OpCode::Nil),
+            15 => Some(OpCode::JumpIfFalse),
+            16 => Some(OpCode::Jump),
+            17 => Some(OpCode::Loop),
+            18 => Some(OpCode::DefineGlobal),
+            19 => Some(OpCode::GetGlobal),
+            20 => Some(OpCode::SetGlobal),
+            21 => Some(OpCode::ConstantLong),
+            22 => Some(OpCode::Pop),
             _ => None,
         }
     }
 
-    pub fn to_usize(&self) -> usize {
-        let value = self.clone();
-        value as usize
+    pub fn to_u8(&self) -> u8 {
+        let value = *self;
+        value as u8
+    }
+
+    /// Human-readable mnemonic used by the disassembler.
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            OpCode::Constant => "CONSTANT",
+            OpCode::Add => "ADD",
+            OpCode::Subtract => "SUBTRACT",
+            OpCode::Multiply => "MULTIPLY",
+            OpCode::Divide => "DIVIDE",
+            OpCode::Negate => "NEGATE",
+            OpCode::Not => "NOT",
+            OpCode::Equal => "EQUAL",
+            OpCode::Greater => "GREATER",
+            OpCode::Less => "LESS",
+            OpCode::And => "AND",
+            OpCode::Or => "OR",
+            OpCode::True => "TRUE",
+            OpCode::False => "FALSE",
+            OpCode::Nil => "NIL",
+            OpCode::JumpIfFalse => "JUMP_IF_FALSE",
+            OpCode::Jump => "JUMP",
+            OpCode::Loop => "LOOP",
+            OpCode::DefineGlobal => "DEFINE_GLOBAL",
+            OpCode::GetGlobal => "GET_GLOBAL",
+            OpCode::SetGlobal => "SET_GLOBAL",
+            OpCode::ConstantLong => "CONSTANT_LONG",
+            OpCode::Pop => "POP",
+        }
+    }
+}
+
+/// A runtime value. Numbers stay unboxed `f64`s; strings are reference-counted
+/// so cloning a value off the constant pool is cheap.
+///
+/// Serializing this type has dependency requirements that `Cargo.toml` enables:
+///
+/// * `serde = { version = "1", features = ["rc"] }` — without the non-default
+///   `rc` feature `Str(Rc<String>)` will not derive `Serialize`/`Deserialize`.
+/// * `num-complex = { version = "0.4", features = ["serde"] }` — for
+///   `Complex(Complex64)`.
+///
+/// Caveat: `Rc` sharing is *not* preserved across a round-trip. Two constants
+/// that pointed at the same `Rc<String>` before [`Chunk::to_bytes`] deserialize
+/// into two independent allocations, since the wire format has no notion of
+/// pointer identity. This only costs extra memory; the values compare equal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Number(f64),
+    Complex(Complex64),
+    Bool(bool),
+    Nil,
+    Str(Rc<String>),
+}
+
+impl Value {
+    /// Promotes a real to a complex with zero imaginary part, leaving an
+    /// already-complex value untouched. Used to line up operand types before a
+    /// mixed real/complex arithmetic op.
+    pub fn promote_complex(&self) -> Option<Complex64> {
+        match self {
+            Value::Number(n) => Some(Complex64::new(*n, 0.0)),
+            Value::Complex(c) => Some(*c),
+            _ => None,
+        }
     }
 }
 
-pub type Value = f64;
+/// A half-open byte range `[start, end)` into the original source, used to map
+/// an instruction back to the text that produced it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
 
+impl Span {
+    fn from_meta(meta: &TokenMeta) -> Span {
+        Span {
+            start: meta.start,
+            end: meta.start + meta.len,
+        }
+    }
+
+    /// Renders `message` above the source line containing this span, with a row
+    /// of carets underlining the offending bytes, in the style of a compiler
+    /// diagnostic.
+    pub fn underline(&self, source: &str, message: &str) -> String {
+        let line_start = source[..self.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[self.start..]
+            .find('\n')
+            .map_or(source.len(), |i| self.start + i);
+        let line = &source[line_start..line_end];
+        let caret_col = self.start - line_start;
+        let caret_len = (self.end - self.start).max(1);
+        let row = source[..self.start].matches('\n').count() + 1;
+        format!(
+            "error: {message}\n --> {row}:{col}\n  | {line}\n  | {pad}{carets}\n",
+            col = caret_col + 1,
+            pad = " ".repeat(caret_col),
+            carets = "^".repeat(caret_len),
+        )
+    }
+}
+
+/// Error returned when a serialized chunk cannot be decoded, either because its
+/// header does not match this build or because the payload is malformed.
 #[derive(Debug)]
+pub enum ChunkDecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Payload(bincode::Error),
+}
+
+/// Magic header identifying a compiled rlox chunk on disk ("RLOX").
+const CHUNK_MAGIC: [u8; 4] = *b"RLOX";
+/// Serialized chunk format version. Bump whenever the layout changes so that
+/// stale files are rejected rather than silently misread.
+const CHUNK_VERSION: u8 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Chunk {
-    pub code: Vec<usize>,
+    pub code: Vec<u8>,
     pub constants: Vec<Value>,
-    pub tokens: Vec<Token>,
+    pub identifiers: Vec<String>,
+    /// Source span for each byte in `code`, used for error reporting.
+    pub spans: Vec<Span>,
 }
 
 impl Chunk {
@@ -66,8 +282,101 @@ impl Chunk {
         Chunk {
             code: Vec::new(),
             constants: Vec::new(),
-            tokens: Vec::new(),
+            identifiers: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Serializes the chunk to a compact binary blob prefixed with the magic
+    /// header and version byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&CHUNK_MAGIC);
+        bytes.push(CHUNK_VERSION);
+        let payload = bincode::serialize(self).expect("chunk is serializable");
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    /// Reverses [`Chunk::to_bytes`], rejecting files whose magic header or
+    /// version does not match this build.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chunk, ChunkDecodeError> {
+        if bytes.len() < CHUNK_MAGIC.len() + 1 {
+            return Err(ChunkDecodeError::Truncated);
+        }
+        if bytes[..CHUNK_MAGIC.len()] != CHUNK_MAGIC {
+            return Err(ChunkDecodeError::BadMagic);
         }
+        let version = bytes[CHUNK_MAGIC.len()];
+        if version != CHUNK_VERSION {
+            return Err(ChunkDecodeError::UnsupportedVersion(version));
+        }
+        let payload = &bytes[CHUNK_MAGIC.len() + 1..];
+        bincode::deserialize(payload).map_err(ChunkDecodeError::Payload)
+    }
+
+    /// Decodes the 3-byte little-endian constant index stored at `pos`, as
+    /// written for `OpCode::ConstantLong`.
+    fn read_long(&self, pos: usize) -> usize {
+        self.code[pos] as usize
+            | (self.code[pos + 1] as usize) << 8
+            | (self.code[pos + 2] as usize) << 16
+    }
+
+    /// Walks `code`, decoding each instruction into a human-readable listing of
+    /// `<offset> <mnemonic> [operand]`. Constant-bearing opcodes show the value
+    /// pulled from `constants`, identifier opcodes show the interned name, and
+    /// jumps show their raw operand.
+    pub fn disassemble(&self) -> String {
+        let mut listing = String::new();
+        let mut offset = 0;
+        while offset < self.code.len() {
+            let op_code = match OpCode::from_u8(self.code[offset]) {
+                Some(op_code) => op_code,
+                None => {
+                    listing.push_str(&format!("{offset:04} <unknown {}>\n", self.code[offset]));
+                    offset += 1;
+                    continue;
+                }
+            };
+            let mnemonic = op_code.mnemonic();
+            match op_code {
+                OpCode::Constant => {
+                    let index = self.code[offset + 1] as usize;
+                    listing.push_str(&format!(
+                        "{offset:04} {mnemonic:<14} {index} ({:?})\n",
+                        self.constants[index]
+                    ));
+                    offset += 2;
+                }
+                OpCode::ConstantLong => {
+                    let index = self.read_long(offset + 1);
+                    listing.push_str(&format!(
+                        "{offset:04} {mnemonic:<14} {index} ({:?})\n",
+                        self.constants[index]
+                    ));
+                    offset += 4;
+                }
+                OpCode::DefineGlobal | OpCode::GetGlobal | OpCode::SetGlobal => {
+                    let index = self.code[offset + 1] as usize;
+                    listing.push_str(&format!(
+                        "{offset:04} {mnemonic:<14} {index} ({})\n",
+                        self.identifiers[index]
+                    ));
+                    offset += 2;
+                }
+                OpCode::JumpIfFalse | OpCode::Jump | OpCode::Loop => {
+                    let jump = (self.code[offset + 1] as usize) << 8 | self.code[offset + 2] as usize;
+                    listing.push_str(&format!("{offset:04} {mnemonic:<14} {jump}\n"));
+                    offset += 3;
+                }
+                _ => {
+                    listing.push_str(&format!("{offset:04} {mnemonic}\n"));
+                    offset += 1;
+                }
+            }
+        }
+        listing
     }
 }
 
@@ -104,23 +413,180 @@ impl Compiler {
         }
     }
 
+    /// Compiles `input` and writes the resulting chunk to `path` in the binary
+    /// format produced by [`Chunk::to_bytes`], so it can be reloaded without
+    /// re-running the front end.
+    pub fn compile_to_file(
+        &mut self,
+        input: String,
+        path: &str,
+    ) -> Result<(), CompilerError> {
+        let bytes = self.compile(input)?.to_bytes();
+        std::fs::write(path, bytes).map_err(CompilerError::Io)
+    }
+
+    /// Appends a single raw byte to `code`, keeping the parallel `spans` vec in
+    /// lockstep so every byte maps back to its source range.
+    fn push_byte(&mut self, byte: u8, token: &Token) {
+        self.chunk.code.push(byte);
+        self.chunk.spans.push(Span::from_meta(&token.meta));
+    }
+
     fn add_instruction(&mut self, op_code: OpCode, token: &Token) {
-        self.chunk.code.push(op_code.to_usize());
-        self.chunk.tokens.push(token.clone());
+        self.push_byte(op_code.to_u8(), token);
+    }
+
+    /// Appends `value` to the constant pool and emits a load for it, choosing
+    /// the single-byte `Constant` form when the index fits in a `u8` and falling
+    /// back to `ConstantLong` with a 3-byte little-endian index otherwise.
+    fn add_constant(&mut self, value: Value, token: &Token) {
+        let index = self.chunk.constants.len();
+        self.chunk.constants.push(value);
+        if index <= u8::MAX as usize {
+            self.add_instruction(OpCode::Constant, token);
+            self.push_byte(index as u8, token);
+        } else {
+            self.add_instruction(OpCode::ConstantLong, token);
+            self.push_byte((index & 0xff) as u8, token);
+            self.push_byte(((index >> 8) & 0xff) as u8, token);
+            self.push_byte(((index >> 16) & 0xff) as u8, token);
+        }
+    }
+
+    /// Returns the index of `name` in the chunk's identifier table, appending it
+    /// first if it has not been seen before so repeated references to the same
+    /// global share a single slot.
+    fn intern_identifier(&mut self, name: &str) -> usize {
+        if let Some(index) = self.chunk.identifiers.iter().position(|id| id == name) {
+            return index;
+        }
+        self.chunk.identifiers.push(name.to_string());
+        self.chunk.identifiers.len() - 1
+    }
+
+    /// Emits `op_code` followed by an operand that indexes into the chunk's
+    /// identifier table.
+    fn add_identifier(&mut self, op_code: OpCode, name: &str, token: &Token) {
+        self.add_instruction(op_code, token);
+        let index = self.intern_identifier(name);
+        self.push_byte(index as u8, token);
+    }
+
+    /// Emits `op_code` followed by a two-byte placeholder operand, returning the
+    /// index of the first placeholder byte so it can be backpatched once the
+    /// jump target is known.
+    fn emit_jump(&mut self, op_code: OpCode, token: &Token) -> usize {
+        self.add_instruction(op_code, token);
+        let slot = self.chunk.code.len();
+        self.push_byte(0, token);
+        self.push_byte(0, token);
+        slot
+    }
+
+    /// Rewrites the two-byte placeholder at `slot` with the forward distance to
+    /// the instruction that will be emitted next.
+    fn patch_jump(&mut self, slot: usize) {
+        let offset = self.chunk.code.len() - slot - 2;
+        self.chunk.code[slot] = ((offset >> 8) & 0xff) as u8;
+        self.chunk.code[slot + 1] = (offset & 0xff) as u8;
     }
 
-    fn add_constant(&mut self, value: &Value, token: &Token) {
-        self.chunk.code.push(self.chunk.constants.len());
-        self.chunk.constants.push(*value);
-        self.chunk.tokens.push(token.clone());
+    /// Emits a `Loop` whose two-byte operand is the backward distance from the
+    /// next instruction to `loop_start`.
+    fn emit_loop(&mut self, loop_start: usize, token: &Token) {
+        self.add_instruction(OpCode::Loop, token);
+        let offset = self.chunk.code.len() + 2 - loop_start;
+        self.push_byte(((offset >> 8) & 0xff) as u8, token);
+        self.push_byte((offset & 0xff) as u8, token);
+    }
+
+    /// Whether `node` is a literal the compiler already knows evaluates to a
+    /// complex value, i.e. an imaginary literal such as `2i`.
+    fn is_complex_literal(node: &Box<AstNode>) -> bool {
+        matches!(node.as_ref(), AstNode::ImaginaryLit { .. })
+    }
+
+    /// Compiles one operand of an arithmetic binary expression. When the other
+    /// operand is complex and this one is a real literal, the real is folded
+    /// into a complex constant here via [`Value::promote_complex`] so both
+    /// sides reach the op as complex values; otherwise it compiles normally.
+    fn compile_arith_operand(
+        &mut self,
+        node: &Box<AstNode>,
+        other_is_complex: bool,
+    ) -> Result<(), CompileError> {
+        if other_is_complex {
+            if let AstNode::NumericLit { token, value } = node.as_ref() {
+                let promoted = Value::Number(*value)
+                    .promote_complex()
+                    .expect("a number always promotes to a complex");
+                self.add_constant(Value::Complex(promoted), token);
+                return Ok(());
+            }
+        }
+        self.compile_ast(node)
+    }
+
+    /// `a and b`: evaluate `a`, and if it is falsy jump past `b` leaving `a`
+    /// on the stack as the result; otherwise pop `a` and fall through to `b`.
+    fn compile_and(
+        &mut self,
+        left: &Box<AstNode>,
+        right: &Box<AstNode>,
+        token: &Token,
+    ) -> Result<(), CompileError> {
+        self.compile_ast(left)?;
+        let end_jump = self.emit_jump(OpCode::JumpIfFalse, token);
+        self.add_instruction(OpCode::Pop, token);
+        self.compile_ast(right)?;
+        self.patch_jump(end_jump);
+        Ok(())
+    }
+
+    /// `a or b`: evaluate `a`, and if it is truthy jump past `b` leaving `a`
+    /// on the stack as the result; otherwise pop `a` and fall through to `b`.
+    fn compile_or(
+        &mut self,
+        left: &Box<AstNode>,
+        right: &Box<AstNode>,
+        token: &Token,
+    ) -> Result<(), CompileError> {
+        self.compile_ast(left)?;
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse, token);
+        let end_jump = self.emit_jump(OpCode::Jump, token);
+        self.patch_jump(else_jump);
+        self.add_instruction(OpCode::Pop, token);
+        self.compile_ast(right)?;
+        self.patch_jump(end_jump);
+        Ok(())
     }
 
     fn compile_ast(&mut self, ast_node: &Box<AstNode>) -> Result<(), CompileError> {
         match ast_node.as_ref() {
             AstNode::Empty => {}
             AstNode::NumericLit { token, value } => {
-                self.add_instruction(OpCode::Constant, token);
-                self.add_constant(value, token);
+                self.add_constant(Value::Number(*value), token);
+            }
+            AstNode::ImaginaryLit { token, value } => {
+                self.add_constant(Value::Complex(Complex64::new(0.0, *value)), token);
+            }
+            AstNode::BoolLit { token, value } => {
+                let op = if *value { OpCode::True } else { OpCode::False };
+                self.add_instruction(op, token);
+            }
+            AstNode::StringLit { token, value } => {
+                self.add_constant(Value::Str(Rc::new(value.clone())), token);
+            }
+            AstNode::VariableAssignmentStmt {
+                token,
+                identifier,
+                expression,
+            } => {
+                self.compile_ast(expression)?;
+                self.add_identifier(OpCode::DefineGlobal, identifier, token);
+            }
+            AstNode::VariableAccessExpr { token, identifier } => {
+                self.add_identifier(OpCode::GetGlobal, identifier, token);
             }
             AstNode::Expr { expr, .. } => {
                 self.compile_ast(expr)?;
@@ -131,14 +597,41 @@ impl Compiler {
                 match &token.token_class {
                     TokenClass::Op(op) => match op {
                         OpToken::Min => self.add_instruction(OpCode::Negate, token),
+                        OpToken::Bang => self.add_instruction(OpCode::Not, token),
                         _ => return Err(CompileError::UnsupportedToken),
                     },
                     _ => return Err(CompileError::ExpectedOpNode),
                 }
             }
             AstNode::BinaryExpr { token, left, right } => {
-                self.compile_ast(left)?;
-                self.compile_ast(right)?;
+                // `and`/`or` short-circuit, so they control their own operand
+                // evaluation and jump layout rather than emitting a plain op.
+                match &token.token_class {
+                    TokenClass::Op(OpToken::And) => return self.compile_and(left, right, token),
+                    TokenClass::Op(OpToken::Or) => return self.compile_or(left, right, token),
+                    _ => {}
+                }
+
+                // Arithmetic ops allow mixing real and complex operands. When
+                // one side is an imaginary literal, a real literal on the other
+                // side is promoted to a complex constant up front. Other mixes
+                // (e.g. a real literal against a complex sub-expression or
+                // variable) are compiled unpromoted.
+                let is_arith = matches!(
+                    &token.token_class,
+                    TokenClass::Op(
+                        OpToken::Plus | OpToken::Min | OpToken::Star | OpToken::Slash
+                    )
+                );
+                if is_arith {
+                    let left_complex = Self::is_complex_literal(left);
+                    let right_complex = Self::is_complex_literal(right);
+                    self.compile_arith_operand(left, right_complex)?;
+                    self.compile_arith_operand(right, left_complex)?;
+                } else {
+                    self.compile_ast(left)?;
+                    self.compile_ast(right)?;
+                }
 
                 match &token.token_class {
                     TokenClass::Op(op) => match op {
@@ -146,13 +639,131 @@ impl Compiler {
                         OpToken::Min => self.add_instruction(OpCode::Subtract, token),
                         OpToken::Star => self.add_instruction(OpCode::Multiply, token),
                         OpToken::Slash => self.add_instruction(OpCode::Divide, token),
+                        OpToken::EqEq => self.add_instruction(OpCode::Equal, token),
+                        OpToken::BangEq => {
+                            self.add_instruction(OpCode::Equal, token);
+                            self.add_instruction(OpCode::Not, token);
+                        }
+                        OpToken::Less => self.add_instruction(OpCode::Less, token),
+                        OpToken::LessEq => {
+                            self.add_instruction(OpCode::Greater, token);
+                            self.add_instruction(OpCode::Not, token);
+                        }
+                        OpToken::Greater => self.add_instruction(OpCode::Greater, token),
+                        OpToken::GreaterEq => {
+                            self.add_instruction(OpCode::Less, token);
+                            self.add_instruction(OpCode::Not, token);
+                        }
                         _ => return Err(CompileError::UnsupportedBinaryOperator),
                     },
                     _ => return Err(CompileError::ExpectedOpNode),
                 }
             }
+            AstNode::Block { statements, .. } => {
+                for statement in statements {
+                    self.compile_ast(statement)?;
+                }
+            }
+            AstNode::IfStmt {
+                token,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.compile_ast(condition)?;
+                let then_jump = self.emit_jump(OpCode::JumpIfFalse, token);
+                self.add_instruction(OpCode::Pop, token);
+                self.compile_ast(then_branch)?;
+                let else_jump = self.emit_jump(OpCode::Jump, token);
+                self.patch_jump(then_jump);
+                self.add_instruction(OpCode::Pop, token);
+                if let Some(else_branch) = else_branch {
+                    self.compile_ast(else_branch)?;
+                }
+                self.patch_jump(else_jump);
+            }
+            AstNode::WhileStmt {
+                token,
+                condition,
+                body,
+            } => {
+                let loop_start = self.chunk.code.len();
+                self.compile_ast(condition)?;
+                let exit_jump = self.emit_jump(OpCode::JumpIfFalse, token);
+                self.add_instruction(OpCode::Pop, token);
+                self.compile_ast(body)?;
+                self.emit_loop(loop_start, token);
+                self.patch_jump(exit_jump);
+                self.add_instruction(OpCode::Pop, token);
+            }
+            _ => return Err(CompileError::UnsupportedToken),
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::AtomToken;
+
+    /// A throwaway token for exercising the emitter directly without running
+    /// the lexer/parser front end.
+    fn dummy_token() -> Token {
+        Token {
+            token_class: TokenClass::Atom(AtomToken::NumericLit),
+            lexeme: String::from("0"),
+            meta: TokenMeta {
+                start_row: 0,
+                start_col: 0,
+                start: 0,
+                len: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn constants_past_the_byte_boundary_use_the_long_form() {
+        let mut compiler = Compiler::new();
+        let token = dummy_token();
+
+        // The first 256 constants (indices 0..=255) fit in a single byte; the
+        // 257th (index 256) must spill to the wide `ConstantLong` form.
+        for index in 0..300 {
+            compiler.add_constant(Value::Number(index as f64), &token);
+        }
+
+        let chunk = &compiler.chunk;
+        assert_eq!(chunk.constants.len(), 300);
+
+        // Walk the instruction stream opcode by opcode (a raw byte scan would
+        // trip over index bytes that happen to equal an opcode value).
+        let mut narrow = 0usize;
+        let mut long = 0usize;
+        let mut first_long_index = None;
+        let mut offset = 0;
+        while offset < chunk.code.len() {
+            match OpCode::from_u8(chunk.code[offset]) {
+                Some(OpCode::Constant) => {
+                    narrow += 1;
+                    offset += 2;
+                }
+                Some(OpCode::ConstantLong) => {
+                    if first_long_index.is_none() {
+                        first_long_index = Some(chunk.read_long(offset + 1));
+                    }
+                    long += 1;
+                    offset += 4;
+                }
+                _ => panic!("unexpected opcode at {offset}"),
+            }
+        }
+
+        // Indices 0..=255 fit the narrow form; 256..=299 spill to the wide one.
+        assert_eq!(narrow, 256, "indices 0..=255 should use the narrow form");
+        assert_eq!(long, 44, "indices 256.. should use the long form");
+        // The first wide operand must decode back to constant index 256.
+        assert_eq!(first_long_index, Some(256));
+    }
+}
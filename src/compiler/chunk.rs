@@ -1,6 +1,6 @@
 use crate::{compiler::op_code::Value, lexer::Token};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Chunk {
     pub code: Vec<usize>,
     pub constants: Vec<Value>,
@@ -9,6 +9,20 @@ pub enum OpCode {
     Negate = 5,
     SetVar = 6,
     GetVar = 7,
+    Pop = 8,
+    Jump = 9,
+    JumpIfFalse = 10,
+    Loop = 11,
+    Not = 12,
+    Equal = 13,
+    Greater = 14,
+    Less = 15,
+    True = 16,
+    False = 17,
+    Print = 18,
+    Call = 19,
+    Return = 20,
+    GetLocal = 21,
 }
 
 impl OpCode {
@@ -22,6 +36,20 @@ impl OpCode {
             5 => Some(OpCode::Negate),
             6 => Some(OpCode::SetVar),
             7 => Some(OpCode::GetVar),
+            8 => Some(OpCode::Pop),
+            9 => Some(OpCode::Jump),
+            10 => Some(OpCode::JumpIfFalse),
+            11 => Some(OpCode::Loop),
+            12 => Some(OpCode::Not),
+            13 => Some(OpCode::Equal),
+            14 => Some(OpCode::Greater),
+            15 => Some(OpCode::Less),
+            16 => Some(OpCode::True),
+            17 => Some(OpCode::False),
+            18 => Some(OpCode::Print),
+            19 => Some(OpCode::Call),
+            20 => Some(OpCode::Return),
+            21 => Some(OpCode::GetLocal),
             _ => None,
         }
     }
@@ -30,10 +58,131 @@ impl OpCode {
         let value = self.clone();
         value as usize
     }
+
+    /// Human-readable mnemonic used by the disassembler.
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            OpCode::Constant => "CONSTANT",
+            OpCode::Add => "ADD",
+            OpCode::Subtract => "SUBTRACT",
+            OpCode::Multiply => "MULTIPLY",
+            OpCode::Divide => "DIVIDE",
+            OpCode::Negate => "NEGATE",
+            OpCode::SetVar => "SET_VAR",
+            OpCode::GetVar => "GET_VAR",
+            OpCode::Pop => "POP",
+            OpCode::Jump => "JUMP",
+            OpCode::JumpIfFalse => "JUMP_IF_FALSE",
+            OpCode::Loop => "LOOP",
+            OpCode::Not => "NOT",
+            OpCode::Equal => "EQUAL",
+            OpCode::Greater => "GREATER",
+            OpCode::Less => "LESS",
+            OpCode::True => "TRUE",
+            OpCode::False => "FALSE",
+            OpCode::Print => "PRINT",
+            OpCode::Call => "CALL",
+            OpCode::Return => "RETURN",
+            OpCode::GetLocal => "GET_LOCAL",
+        }
+    }
+}
+
+use std::rc::Rc;
+
+use crate::compiler::chunk::Chunk;
+
+/// Walks `chunk.code`, decoding each instruction and its operands into a
+/// human-readable listing with instruction offsets. Constant-bearing opcodes
+/// print the referenced value from `chunk.constants`; jumps print their decoded
+/// offset.
+pub fn disassemble(chunk: &Chunk) -> String {
+    let mut listing = String::new();
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let op_code = match OpCode::from_usize(chunk.code[offset]) {
+            Some(op_code) => op_code,
+            None => {
+                listing.push_str(&format!("{offset:04} <unknown {}>\n", chunk.code[offset]));
+                offset += 1;
+                continue;
+            }
+        };
+        let mnemonic = op_code.mnemonic();
+        match op_code {
+            OpCode::Constant | OpCode::SetVar | OpCode::GetVar => {
+                let constant_idx = chunk.code[offset + 1];
+                listing.push_str(&format!(
+                    "{offset:04} {mnemonic:<14} {constant_idx} ({:?})\n",
+                    chunk.constants[constant_idx]
+                ));
+                offset += 2;
+            }
+            OpCode::GetLocal | OpCode::Call => {
+                let operand = chunk.code[offset + 1];
+                listing.push_str(&format!("{offset:04} {mnemonic:<14} {operand}\n"));
+                offset += 2;
+            }
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::Loop => {
+                let jump = (chunk.code[offset + 1] << 8) | chunk.code[offset + 2];
+                listing.push_str(&format!("{offset:04} {mnemonic:<14} {jump}\n"));
+                offset += 3;
+            }
+            _ => {
+                listing.push_str(&format!("{offset:04} {mnemonic}\n"));
+                offset += 1;
+            }
+        }
+    }
+    listing
 }
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Number(f64),
+    Bool(bool),
     String(String),
+    Function(Rc<FunctionObj>),
+    Builtin(Builtin),
+}
+
+impl Value {
+    /// Returns a dispatchable view of the value when it can be invoked, so the
+    /// interpreter can treat user functions and natives uniformly at a call.
+    pub fn as_callable(&self) -> Option<Callable> {
+        match self {
+            Value::Function(function) => Some(Callable::Function(function.clone())),
+            Value::Builtin(builtin) => Some(Callable::Builtin(builtin.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// A user-defined function: its own compiled `Chunk` plus the number of
+/// parameters it expects.
+#[derive(Debug, Clone)]
+pub struct FunctionObj {
+    pub name: String,
+    pub arity: usize,
+    pub chunk: Chunk,
+}
+
+/// A native function implemented in Rust, e.g. `clock()`.
+#[derive(Clone)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    pub func: fn(&[Value]) -> Value,
+}
+
+impl std::fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
+
+/// The two kinds of things a `Call` can target.
+pub enum Callable {
+    Builtin(Builtin),
+    Function(Rc<FunctionObj>),
 }
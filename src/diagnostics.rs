@@ -0,0 +1,58 @@
+use crate::lexer::TokenMeta;
+
+/// How serious a diagnostic is. Only errors exist today, but the severity is
+/// kept explicit so warnings can be layered in without touching the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+}
+
+impl Severity {
+    fn label(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single problem tied to a source span, ready to be rendered against the
+/// original input. The span is a [`TokenMeta`] so it carries the row/column of
+/// the offending text and how many characters it covers.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub meta: TokenMeta,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, meta: TokenMeta) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            meta,
+        }
+    }
+
+    /// Renders the diagnostic against `source`, echoing the offending line and
+    /// underlining the span with carets beneath it, à la Rust/rhai output. The
+    /// row indexes into the accumulated REPL input, so multi-line buffers pick
+    /// out the correct line.
+    pub fn render(&self, source: &str) -> String {
+        let line = source.lines().nth(self.meta.start_row).unwrap_or("");
+        let row = self.meta.start_row + 1;
+        let col = self.meta.start_col + 1;
+        // a zero-length span (e.g. end-of-file) still gets a single caret
+        let width = self.meta.len.max(1);
+        let underline = format!(
+            "{}{}",
+            " ".repeat(self.meta.start_col),
+            "^".repeat(width)
+        );
+        format!(
+            "{severity}: {message}\n --> {row}:{col}\n  | {line}\n  | {underline}",
+            severity = self.severity.label(),
+            message = self.message,
+        )
+    }
+}
@@ -1,14 +1,58 @@
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{
     compiler::{
         chunk::Chunk,
         compiler::{Compiler, CompilerError},
-        op_code::{OpCode, Value},
+        op_code::{disassemble, Builtin, Callable, FunctionObj, OpCode, Value},
     },
+    diagnostics::Diagnostic,
+    lexer::{Lexer, LexerError, TokenMeta},
+    parser::{ast::ParserError, parser::Parser},
     repl::{Evaluator, EvaluatorOk},
 };
 
+/// Which pipeline stage a REPL `:tokens`/`:ast`/`:bytecode` command (or the
+/// `--emit` CLI flag) dumps instead of executing the source.
+#[derive(Debug, Clone, Copy)]
+pub enum Inspect {
+    Tokens,
+    Ast,
+    Bytecode,
+}
+
+impl Inspect {
+    /// Parses the value of the `--emit=` flag.
+    pub fn from_flag(flag: &str) -> Option<Inspect> {
+        match flag {
+            "tokens" => Some(Inspect::Tokens),
+            "ast" => Some(Inspect::Ast),
+            "bytecode" => Some(Inspect::Bytecode),
+            _ => None,
+        }
+    }
+
+    /// Parses a REPL meta-command such as `:bytecode`.
+    fn from_command(command: &str) -> Option<Inspect> {
+        match command {
+            ":tokens" => Some(Inspect::Tokens),
+            ":ast" => Some(Inspect::Ast),
+            ":bytecode" => Some(Inspect::Bytecode),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Inspect::Tokens => "tokens",
+            Inspect::Ast => "AST",
+            Inspect::Bytecode => "bytecode",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum RuntimeError {
     InvalidOpCode(usize),
@@ -18,26 +62,110 @@ pub enum RuntimeError {
     ExpectedOperand,
     ExpectedExpression,
     UninitialisedVariable,
+    InvalidOperandType,
+    ArityMismatch,
+    NotCallable,
 }
 
 #[derive(Debug)]
 pub enum InterpreterError {
     Compiler(CompilerError),
-    Runtime(RuntimeError),
+    /// A runtime fault paired with the span of the instruction that raised it,
+    /// recovered from the executing chunk's parallel `tokens` vec.
+    Runtime {
+        error: RuntimeError,
+        meta: Option<TokenMeta>,
+    },
+}
+
+/// A single activation record. `stack_base` marks the slot holding the callee
+/// (slot 0 of the frame); the function's parameters follow it on the shared
+/// stack.
+struct CallFrame {
+    function: Rc<FunctionObj>,
+    ip: usize,
+    stack_base: usize,
 }
 
 pub struct Interpreter {
     variables: HashMap<String, Value>,
     stack: Vec<Value>,
+    frames: Vec<CallFrame>,
     ip: usize,
+    /// Span of the instruction currently being executed, attached to any
+    /// runtime error raised while it is the active instruction.
+    error_span: Option<TokenMeta>,
+    /// Set by a `:tokens`/`:ast`/`:bytecode` meta-command; the next entered
+    /// expression is dumped at this stage rather than executed.
+    pending_inspect: Option<Inspect>,
+}
+
+/// The native `clock()` builtin: seconds since the Unix epoch.
+fn builtin_clock(_args: &[Value]) -> Value {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.0);
+    Value::Number(seconds)
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
+        let mut variables = HashMap::new();
+        variables.insert(
+            "clock".to_string(),
+            Value::Builtin(Builtin {
+                name: "clock",
+                arity: 0,
+                func: builtin_clock,
+            }),
+        );
         Interpreter {
-            variables: HashMap::new(),
+            variables,
             stack: Vec::with_capacity(1024),
+            frames: Vec::new(),
             ip: 0,
+            error_span: None,
+            pending_inspect: None,
+        }
+    }
+
+    /// Runs `source` up to `stage` and returns the dump (token stream, AST, or
+    /// disassembled bytecode) as a string, rendering any failure as a
+    /// diagnostic against `source`. Backs both the REPL meta-commands and the
+    /// `--emit` CLI flag.
+    pub fn emit(&self, source: String, stage: Inspect) -> Result<String, String> {
+        match self.render_stage(source.clone(), stage) {
+            Ok(listing) => Ok(listing),
+            Err(err) => Err(err.to_diagnostic().render(&source)),
+        }
+    }
+
+    fn render_stage(&self, source: String, stage: Inspect) -> Result<String, InterpreterError> {
+        match stage {
+            Inspect::Tokens => {
+                let mut lexer = Lexer::new(source);
+                lexer
+                    .tokenize()
+                    .map_err(|err| InterpreterError::Compiler(CompilerError::Lexer(err)))?;
+                Ok(format!("{:#?}", lexer.tokens))
+            }
+            Inspect::Ast => {
+                let mut lexer = Lexer::new(source);
+                lexer
+                    .tokenize()
+                    .map_err(|err| InterpreterError::Compiler(CompilerError::Lexer(err)))?;
+                let mut parser = Parser::new(&lexer.tokens);
+                let ast = parser
+                    .parse()
+                    .map_err(|err| InterpreterError::Compiler(CompilerError::Parser(err)))?;
+                Ok(format!("{:#?}", ast))
+            }
+            Inspect::Bytecode => {
+                let mut compiler = Compiler::new();
+                let chunk = compiler.compile(source).map_err(InterpreterError::Compiler)?;
+                Ok(disassemble(chunk))
+            }
         }
     }
 
@@ -45,25 +173,49 @@ impl Interpreter {
         let mut compiler = Compiler::new();
         let chunk = match compiler.compile(input) {
             Err(err) => return Err(InterpreterError::Compiler(err)),
-            Ok(chunk) => chunk,
+            Ok(chunk) => chunk.clone(),
         };
-        let result = self.interpret_chunk(chunk)?;
+        let result = self.run(chunk)?;
         match result {
-            Some(Value::Number(result)) => Ok(result.to_string()),
+            Some(value) => Ok(Self::display(&value)),
             None => Ok("".to_string()),
-            _ => todo!(),
         }
     }
 
-    fn interpret_chunk(&mut self, chunk: &Chunk) -> Result<Option<Value>, InterpreterError> {
+    fn run(&mut self, chunk: Chunk) -> Result<Option<Value>, InterpreterError> {
         self.stack.clear();
+        self.frames.clear();
         self.ip = 0;
-        while self.ip < chunk.code.len() {
+
+        // the top-level script runs as an implicit zero-argument function
+        let script = Rc::new(FunctionObj {
+            name: "<script>".to_string(),
+            arity: 0,
+            chunk,
+        });
+        self.frames.push(CallFrame {
+            function: script,
+            ip: 0,
+            stack_base: 0,
+        });
+
+        loop {
+            let function = self.frames.last().unwrap().function.clone();
+            let stack_base = self.frames.last().unwrap().stack_base;
+            let chunk = &function.chunk;
+
+            if self.ip >= chunk.code.len() {
+                // only the top-level frame falls off the end; functions return
+                break;
+            }
+
+            // remember where this instruction came from so a fault can point
+            // back at the offending source span
+            self.error_span = chunk.tokens.get(self.ip).map(|token| token.meta.clone());
+
             let op_code = match OpCode::from_usize(chunk.code[self.ip]) {
                 None => {
-                    return Err(InterpreterError::Runtime(RuntimeError::InvalidOpCode(
-                        chunk.code[self.ip],
-                    )));
+                    return Err(self.runtime_error(RuntimeError::InvalidOpCode(chunk.code[self.ip])));
                 }
                 Some(op_code) => op_code,
             };
@@ -76,32 +228,93 @@ impl Interpreter {
                     self.ip += 2;
                 }
                 OpCode::Negate => match self.stack.pop() {
-                    None => return Err(InterpreterError::Runtime(RuntimeError::ExpectedOperand)),
+                    None => return Err(self.runtime_error(RuntimeError::ExpectedOperand)),
                     Some(operand) => {
                         self.ip += 1;
                         match operand {
                             Value::Number(operand) => self.stack.push(Value::Number(-operand)),
-                            _ => todo!(),
+                            _ => {
+                                return Err(self.runtime_error(RuntimeError::InvalidOperandType));
+                            }
+                        }
+                    }
+                },
+                OpCode::True => {
+                    self.stack.push(Value::Bool(true));
+                    self.ip += 1;
+                }
+                OpCode::False => {
+                    self.stack.push(Value::Bool(false));
+                    self.ip += 1;
+                }
+                OpCode::Not => match self.stack.pop() {
+                    None => return Err(self.runtime_error(RuntimeError::ExpectedOperand)),
+                    Some(operand) => {
+                        self.stack.push(Value::Bool(!Self::is_truthy(&operand)));
+                        self.ip += 1;
+                    }
+                },
+                OpCode::Equal => {
+                    let (right, left) = (self.stack.pop(), self.stack.pop());
+                    match (left, right) {
+                        (Some(left), Some(right)) => {
+                            self.stack.push(Value::Bool(Self::values_equal(&left, &right)));
+                            self.ip += 1;
                         }
+                        _ => {
+                            return Err(self.runtime_error(RuntimeError::ExpectedOperand));
+                        }
+                    }
+                }
+                OpCode::Greater | OpCode::Less => self.interpret_comparison(op_code)?,
+                OpCode::Print => match self.stack.pop() {
+                    None => return Err(self.runtime_error(RuntimeError::ExpectedOperand)),
+                    Some(value) => {
+                        println!("{}", Self::display(&value));
+                        self.ip += 1;
                     }
                 },
                 OpCode::Add | OpCode::Subtract | OpCode::Multiply | OpCode::Divide => {
                     self.interpret_binary_op(op_code)?
                 }
+                OpCode::Pop => {
+                    self.stack.pop();
+                    self.ip += 1;
+                }
+                OpCode::Jump => {
+                    let offset = Self::read_offset(chunk, self.ip);
+                    self.ip += 3 + offset;
+                }
+                OpCode::JumpIfFalse => {
+                    let offset = Self::read_offset(chunk, self.ip);
+                    let condition = match self.stack.last() {
+                        Some(condition) => condition,
+                        None => {
+                            return Err(self.runtime_error(RuntimeError::ExpectedOperand));
+                        }
+                    };
+                    if Self::is_truthy(condition) {
+                        self.ip += 3;
+                    } else {
+                        self.ip += 3 + offset;
+                    }
+                }
+                OpCode::Loop => {
+                    let offset = Self::read_offset(chunk, self.ip);
+                    self.ip = self.ip + 3 - offset;
+                }
                 OpCode::SetVar => {
                     let constant_idx = chunk.code[self.ip + 1];
                     let var_name = match &chunk.constants[constant_idx] {
                         Value::String(var_name) => var_name,
                         _ => {
-                            return Err(InterpreterError::Runtime(RuntimeError::InvalidIdentifier));
+                            return Err(self.runtime_error(RuntimeError::InvalidIdentifier));
                         }
                     };
                     let expr_value = match self.stack.pop() {
                         Some(expr_value) => expr_value,
                         None => {
-                            return Err(InterpreterError::Runtime(
-                                RuntimeError::ExpectedExpression,
-                            ));
+                            return Err(self.runtime_error(RuntimeError::ExpectedExpression));
                         }
                     };
                     self.variables.insert(var_name.clone(), expr_value);
@@ -113,23 +326,42 @@ impl Interpreter {
                         Value::String(var_name) => match self.variables.get(var_name) {
                             Some(value) => self.stack.push(value.clone()),
                             None => {
-                                return Err(InterpreterError::Runtime(
-                                    RuntimeError::UninitialisedVariable,
-                                ));
+                                return Err(self.runtime_error(RuntimeError::UninitialisedVariable));
                             }
                         },
                         _ => {
-                            return Err(InterpreterError::Runtime(RuntimeError::InvalidIdentifier));
+                            return Err(self.runtime_error(RuntimeError::InvalidIdentifier));
                         }
                     };
                     self.ip += 2;
                 }
+                OpCode::GetLocal => {
+                    let slot = chunk.code[self.ip + 1];
+                    let value = self.stack[stack_base + slot].clone();
+                    self.stack.push(value);
+                    self.ip += 2;
+                }
+                OpCode::Call => {
+                    let argc = chunk.code[self.ip + 1];
+                    self.ip += 2;
+                    self.call(argc)?;
+                }
+                OpCode::Return => {
+                    let result = match self.stack.pop() {
+                        Some(result) => result,
+                        None => {
+                            return Err(self.runtime_error(RuntimeError::ExpectedOperand));
+                        }
+                    };
+                    let frame = self.frames.pop().unwrap();
+                    self.stack.truncate(frame.stack_base);
+                    self.stack.push(result);
+                    self.ip = self.frames.last().unwrap().ip;
+                }
             }
         }
         if self.stack.len() > 1 {
-            return Err(InterpreterError::Runtime(
-                RuntimeError::IncompleteExpression,
-            ));
+            return Err(self.runtime_error(RuntimeError::IncompleteExpression));
         }
         if self.stack.len() == 1 {
             return Ok(Some(self.stack[0].clone()));
@@ -137,36 +369,229 @@ impl Interpreter {
         Ok(None)
     }
 
+    /// Dispatches a `Call`: the callee sits just below its `argc` arguments on
+    /// the stack. User functions push a new frame; builtins run in place.
+    fn call(&mut self, argc: usize) -> Result<(), InterpreterError> {
+        let callee_index = self.stack.len() - argc - 1;
+        let callee = self.stack[callee_index].clone();
+        match callee.as_callable() {
+            Some(Callable::Function(function)) => {
+                if function.arity != argc {
+                    return Err(self.runtime_error(RuntimeError::ArityMismatch));
+                }
+                self.frames.last_mut().unwrap().ip = self.ip;
+                self.frames.push(CallFrame {
+                    function,
+                    ip: 0,
+                    stack_base: callee_index,
+                });
+                self.ip = 0;
+            }
+            Some(Callable::Builtin(builtin)) => {
+                if builtin.arity != argc {
+                    return Err(self.runtime_error(RuntimeError::ArityMismatch));
+                }
+                let args = self.stack[callee_index + 1..].to_vec();
+                let result = (builtin.func)(&args);
+                self.stack.truncate(callee_index);
+                self.stack.push(result);
+            }
+            None => return Err(self.runtime_error(RuntimeError::NotCallable)),
+        }
+        Ok(())
+    }
+
+    /// Wraps a runtime fault together with the span of the instruction being
+    /// executed so the REPL can underline the offending source.
+    fn runtime_error(&self, error: RuntimeError) -> InterpreterError {
+        InterpreterError::Runtime {
+            error,
+            meta: self.error_span.clone(),
+        }
+    }
+
+    /// Decodes the two-slot operand of a jump instruction at `ip`.
+    fn read_offset(chunk: &Chunk, ip: usize) -> usize {
+        (chunk.code[ip + 1] << 8) | chunk.code[ip + 2]
+    }
+
+    /// The single truthiness rule shared by conditionals, `!`, and the
+    /// logical operators: `false`, the number `0`, and the empty string are
+    /// falsy; everything else is truthy.
+    fn is_truthy(value: &Value) -> bool {
+        match value {
+            Value::Bool(boolean) => *boolean,
+            Value::Number(number) => *number != 0.0,
+            Value::String(string) => !string.is_empty(),
+            Value::Function(_) | Value::Builtin(_) => true,
+        }
+    }
+
+    /// Renders a value the way it should surface to the user, shared by
+    /// `print` and the REPL result line.
+    fn display(value: &Value) -> String {
+        match value {
+            Value::Number(number) => number.to_string(),
+            Value::Bool(boolean) => boolean.to_string(),
+            Value::String(string) => string.clone(),
+            Value::Function(function) => format!("<fn {}>", function.name),
+            Value::Builtin(builtin) => format!("<builtin {}>", builtin.name),
+        }
+    }
+
+    fn values_equal(left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            _ => false,
+        }
+    }
+
     fn interpret_binary_op(&mut self, op_code: OpCode) -> Result<(), InterpreterError> {
+        // pop order flipped
+        let operands = (self.stack.pop(), self.stack.pop());
+        match operands {
+            (Some(Value::Number(right)), Some(Value::Number(left))) => {
+                let result = match op_code {
+                    OpCode::Add => left + right,
+                    OpCode::Subtract => left - right,
+                    OpCode::Divide => left / right,
+                    OpCode::Multiply => left * right,
+                    _ => {
+                        return Err(self.runtime_error(RuntimeError::InvalidBinaryOperator));
+                    }
+                };
+                self.stack.push(Value::Number(result));
+            }
+            (Some(Value::String(right)), Some(Value::String(left)))
+                if matches!(op_code, OpCode::Add) =>
+            {
+                self.stack.push(Value::String(left + &right));
+            }
+            _ => return Err(self.runtime_error(RuntimeError::ExpectedOperand)),
+        };
+
+        self.ip += 1;
+        Ok(())
+    }
+
+    fn interpret_comparison(&mut self, op_code: OpCode) -> Result<(), InterpreterError> {
         // pop order flipped
         let operands = (self.stack.pop(), self.stack.pop());
         let result = match operands {
             (Some(Value::Number(right)), Some(Value::Number(left))) => match op_code {
-                OpCode::Add => left + right,
-                OpCode::Subtract => left - right,
-                OpCode::Divide => left / right,
-                OpCode::Multiply => left * right,
+                OpCode::Greater => left > right,
+                OpCode::Less => left < right,
                 _ => {
-                    return Err(InterpreterError::Runtime(
-                        RuntimeError::InvalidBinaryOperator,
-                    ));
+                    return Err(self.runtime_error(RuntimeError::InvalidBinaryOperator));
                 }
             },
-            _ => return Err(InterpreterError::Runtime(RuntimeError::ExpectedOperand)),
+            _ => return Err(self.runtime_error(RuntimeError::InvalidOperandType)),
         };
 
         self.ip += 1;
-        self.stack.push(Value::Number(result));
+        self.stack.push(Value::Bool(result));
         Ok(())
     }
 }
 
+impl InterpreterError {
+    /// Lowers the error into a [`Diagnostic`], pulling a message and source
+    /// span out of whichever layer raised it. Faults without a span (e.g. a
+    /// truncated token stream) fall back to the start of the input.
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            InterpreterError::Compiler(CompilerError::Lexer(error)) => {
+                Diagnostic::error(lexer_message(error), lexer_meta(error).clone())
+            }
+            InterpreterError::Compiler(CompilerError::Parser(error)) => {
+                let (message, meta) = parser_message_meta(error);
+                Diagnostic::error(message, meta)
+            }
+            InterpreterError::Compiler(CompilerError::Compiler(error)) => {
+                Diagnostic::error(format!("compile error: {:?}", error), TokenMeta::default())
+            }
+            InterpreterError::Runtime { error, meta } => {
+                Diagnostic::error(format!("{:?}", error), meta.clone().unwrap_or_default())
+            }
+        }
+    }
+}
+
+fn lexer_meta(error: &LexerError) -> &TokenMeta {
+    match error {
+        LexerError::UnexpectedEndOfFile { meta }
+        | LexerError::UnexpectedCharacter { meta, .. }
+        | LexerError::InvalidNumericLit { meta, .. }
+        | LexerError::UnterminatedString { meta }
+        | LexerError::MalformedEscape { meta, .. } => meta,
+    }
+}
+
+fn lexer_message(error: &LexerError) -> String {
+    match error {
+        LexerError::UnexpectedEndOfFile { .. } => "unexpected end of input".to_string(),
+        LexerError::UnexpectedCharacter { char, .. } => {
+            format!("unexpected character `{char}`")
+        }
+        LexerError::InvalidNumericLit { char, .. } => {
+            format!("invalid numeric literal near `{char}`")
+        }
+        LexerError::UnterminatedString { .. } => "unterminated string literal".to_string(),
+        LexerError::MalformedEscape { char, .. } => format!("unknown escape sequence `\\{char}`"),
+    }
+}
+
+fn parser_message_meta(error: &ParserError) -> (String, TokenMeta) {
+    match error {
+        ParserError::UnexpectedEndOfTokenStream => {
+            ("unexpected end of input".to_string(), TokenMeta::default())
+        }
+        ParserError::ExpectedEoF { token } => {
+            (format!("expected end of input, found `{}`", token.lexeme), token.meta.clone())
+        }
+        ParserError::ExpectedExpression { token } => {
+            (format!("expected an expression, found `{}`", token.lexeme), token.meta.clone())
+        }
+        ParserError::ExpectedOpToken { token } => {
+            (format!("expected an operator, found `{}`", token.lexeme), token.meta.clone())
+        }
+        ParserError::UnclosedExpression { token } => {
+            ("unclosed expression".to_string(), token.meta.clone())
+        }
+        ParserError::UnexpectedToken { token, expected } => {
+            let message = match expected {
+                Some(expected) => format!("unexpected `{}`, expected {:?}", token.lexeme, expected),
+                None => format!("unexpected token `{}`", token.lexeme),
+            };
+            (message, token.meta.clone())
+        }
+        ParserError::UnexpectedUnaryOperator { token } => {
+            (format!("`{}` is not a unary operator", token.lexeme), token.meta.clone())
+        }
+        ParserError::UnhandledToken { token } => {
+            (format!("unhandled token `{}`", token.lexeme), token.meta.clone())
+        }
+    }
+}
+
 impl Evaluator for Interpreter {
     fn eval(&mut self, input: String) -> Result<EvaluatorOk, String> {
-        let interpret_result = self.interpret(input);
-        match interpret_result {
+        if let Some(stage) = Inspect::from_command(input.trim()) {
+            self.pending_inspect = Some(stage);
+            return Ok(EvaluatorOk::Clear(format!(
+                "next expression will be shown as {}",
+                stage.label()
+            )));
+        }
+        if let Some(stage) = self.pending_inspect.take() {
+            return Ok(EvaluatorOk::Clear(self.emit(input, stage)?));
+        }
+        let source = input.clone();
+        match self.interpret(input) {
             Ok(result) => Ok(EvaluatorOk::Clear(result)),
-            Err(err) => Err(format!("{:#?}", err)),
+            Err(err) => Err(err.to_diagnostic().render(&source)),
         }
     }
 }
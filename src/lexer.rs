@@ -1,38 +1,66 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenMeta {
-    row: usize,
-    col: usize,
+    pub start_row: usize,
+    pub start_col: usize,
+    /// Absolute byte offset of the token's first character into the input.
+    pub start: usize,
+    pub len: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DelimToken {
     Semicolon,
+    Comma,
     EoF,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OpToken {
     LeftParen,
     RightParen,
+    LeftBrace,
+    RightBrace,
     Plus,
     Min,
     Slash,
     Star,
     Eq,
+    EqEq,
+    BangEq,
+    Bang,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    And,
+    Or,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AtomToken {
     NumericLit,
+    ImaginaryLit,
+    BoolLit,
+    StringLit,
     Identifier,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeywordToken {
     Let,
+    If,
+    Else,
+    While,
+    Loop,
+    Break,
+    Continue,
+    Print,
+    Fun,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TokenClass {
     Delim(DelimToken),
     Op(OpToken),
@@ -45,9 +73,11 @@ pub enum LexerError {
     UnexpectedEndOfFile { meta: TokenMeta },
     UnexpectedCharacter { char: String, meta: TokenMeta },
     InvalidNumericLit { char: String, meta: TokenMeta },
+    UnterminatedString { meta: TokenMeta },
+    MalformedEscape { char: String, meta: TokenMeta },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub token_class: TokenClass,
     pub lexeme: String,
@@ -85,14 +115,21 @@ impl Lexer {
         self.col += by;
     }
 
+    /// Builds a span anchored at the current cursor covering `len` characters.
+    fn meta_at(&self, len: usize) -> TokenMeta {
+        TokenMeta {
+            start_row: self.row,
+            start_col: self.col,
+            start: self.pos,
+            len,
+        }
+    }
+
     fn peek(&self) -> Result<&str, LexerError> {
         match self.input.get(self.pos..self.pos + 1) {
             Some(top) => Ok(top),
             None => Err(LexerError::UnexpectedEndOfFile {
-                meta: TokenMeta {
-                    row: self.row,
-                    col: self.col,
-                },
+                meta: self.meta_at(1),
             }),
         }
     }
@@ -101,10 +138,7 @@ impl Lexer {
         match self.input.get(at..at + 1) {
             Some(top) => Ok(top),
             None => Err(LexerError::UnexpectedEndOfFile {
-                meta: TokenMeta {
-                    row: self.row,
-                    col: self.col,
-                },
+                meta: self.meta_at(1),
             }),
         }
     }
@@ -112,27 +146,28 @@ impl Lexer {
     fn create_unexpected_char_err(&self, lexeme: &str) -> LexerError {
         LexerError::UnexpectedCharacter {
             char: lexeme.to_string(),
-            meta: TokenMeta {
-                row: self.row,
-                col: self.col,
-            },
+            meta: self.meta_at(lexeme.len()),
         }
     }
 
     fn push_token(&mut self, token_class: TokenClass, lexeme: &str) {
+        self.push_token_spanned(token_class, lexeme, lexeme.len());
+    }
+
+    /// Pushes a token whose source span differs from its lexeme length, e.g. a
+    /// string literal whose decoded value is shorter than the quoted source.
+    fn push_token_spanned(&mut self, token_class: TokenClass, lexeme: &str, len: usize) {
         self.tokens.push(Token {
             token_class,
             lexeme: lexeme.to_string(),
-            meta: TokenMeta {
-                row: self.row,
-                col: self.col,
-            },
+            meta: self.meta_at(len),
         });
     }
 
     fn scan_delimiter(&mut self, lexeme: String) -> Result<(), LexerError> {
         match lexeme.as_str() {
             ";" => self.push_token(TokenClass::Delim(DelimToken::Semicolon), &lexeme),
+            "," => self.push_token(TokenClass::Delim(DelimToken::Comma), &lexeme),
             _ => return Err(self.create_unexpected_char_err(&lexeme)),
         }
         self.advance(lexeme.len());
@@ -143,13 +178,33 @@ impl Lexer {
         match lexeme.as_str() {
             "(" => self.push_token(TokenClass::Op(OpToken::LeftParen), &lexeme),
             ")" => self.push_token(TokenClass::Op(OpToken::RightParen), &lexeme),
-            "=" => self.push_token(TokenClass::Op(OpToken::Eq), &lexeme),
+            "{" => self.push_token(TokenClass::Op(OpToken::LeftBrace), &lexeme),
+            "}" => self.push_token(TokenClass::Op(OpToken::RightBrace), &lexeme),
             _ => return Err(self.create_unexpected_char_err(&lexeme)),
         }
         self.advance(lexeme.len());
         Ok(())
     }
 
+    fn scan_cmp(&mut self) -> Result<(), LexerError> {
+        let first = self.peek()?.to_string();
+        let two = self.input.get(self.pos..self.pos + 2);
+        let (op, lexeme) = match (first.as_str(), two) {
+            ("=", Some("==")) => (OpToken::EqEq, "=="),
+            ("=", _) => (OpToken::Eq, "="),
+            ("!", Some("!=")) => (OpToken::BangEq, "!="),
+            ("!", _) => (OpToken::Bang, "!"),
+            ("<", Some("<=")) => (OpToken::LessEq, "<="),
+            ("<", _) => (OpToken::Less, "<"),
+            (">", Some(">=")) => (OpToken::GreaterEq, ">="),
+            (">", _) => (OpToken::Greater, ">"),
+            _ => return Err(self.create_unexpected_char_err(&first)),
+        };
+        self.push_token(TokenClass::Op(op), lexeme);
+        self.advance(lexeme.len());
+        Ok(())
+    }
+
     fn scan_binary_op(&mut self, lexeme: String) -> Result<(), LexerError> {
         match lexeme.as_str() {
             "+" => self.push_token(TokenClass::Op(OpToken::Plus), &lexeme),
@@ -162,6 +217,61 @@ impl Lexer {
         Ok(())
     }
 
+    fn scan_string(&mut self) -> Result<(), LexerError> {
+        let mut decoded = String::new();
+        let mut cursor = self.pos + 1;
+
+        loop {
+            let curr = match self.input.get(cursor..cursor + 1) {
+                Some(curr) => curr,
+                None => {
+                    return Err(LexerError::UnterminatedString {
+                        meta: self.meta_at(cursor - self.pos),
+                    });
+                }
+            };
+            match curr {
+                "\"" => {
+                    cursor += 1;
+                    break;
+                }
+                "\\" => {
+                    let escape = match self.input.get(cursor + 1..cursor + 2) {
+                        Some(escape) => escape,
+                        None => {
+                            return Err(LexerError::UnterminatedString {
+                                meta: self.meta_at(cursor - self.pos),
+                            });
+                        }
+                    };
+                    match escape {
+                        "n" => decoded.push('\n'),
+                        "t" => decoded.push('\t'),
+                        "\\" => decoded.push('\\'),
+                        "\"" => decoded.push('"'),
+                        _ => {
+                            return Err(LexerError::MalformedEscape {
+                                char: escape.to_string(),
+                                meta: self.meta_at(cursor - self.pos + 2),
+                            });
+                        }
+                    }
+                    cursor += 2;
+                }
+                _ => {
+                    decoded.push_str(curr);
+                    cursor += 1;
+                }
+            }
+        }
+
+        let delta = cursor - self.pos;
+        self.push_token_spanned(TokenClass::Atom(AtomToken::StringLit), &decoded, delta);
+        self.advance(delta);
+
+        Ok(())
+    }
+
     fn scan_num_lit(&mut self) -> Result<(), LexerError> {
         let mut end = self.pos + 1;
         let mut is_float = false;
@@ -174,10 +284,7 @@ impl Lexer {
                     if is_float {
                         return Err(LexerError::InvalidNumericLit {
                             char: curr.to_string(),
-                            meta: TokenMeta {
-                                row: self.row,
-                                col: self.col,
-                            },
+                            meta: self.meta_at(end - self.pos),
                         });
                     }
                     is_float = true;
@@ -187,41 +294,51 @@ impl Lexer {
             }
         }
 
-        let delta = end - self.pos;
+        // A trailing `i` marks an imaginary literal, e.g. `2i` or `3.5i`. The
+        // lexeme keeps only the numeric part so it parses straight to `f64`.
+        let imaginary = matches!(self.input.get(end..end + 1), Some("i"));
         let lexeme = self.input.get(self.pos..end).unwrap().to_string();
-        self.push_token(TokenClass::Atom(AtomToken::NumericLit), &lexeme);
+        let delta = end - self.pos + if imaginary { 1 } else { 0 };
+        let token_class = if imaginary {
+            TokenClass::Atom(AtomToken::ImaginaryLit)
+        } else {
+            TokenClass::Atom(AtomToken::NumericLit)
+        };
+        self.push_token_spanned(token_class, &lexeme, delta);
         self.advance(delta);
 
         Ok(())
     }
 
-    fn scan_keyword(&mut self) -> Result<(), LexerError> {
-        let lexeme = self.peek()?;
-        match lexeme {
-            "l" if self.input.get(self.pos..self.pos + 3) == Some("let") => {
-                self.push_token(TokenClass::Keyword(KeywordToken::Let), "let");
-                self.advance(3);
-            }
-            _ => self.scan_identifier()?,
-        }
-
-        Ok(())
-    }
-
-    fn scan_identifier(&mut self) -> Result<(), LexerError> {
+    fn scan_word(&mut self) -> Result<(), LexerError> {
         let mut end = self.pos + 1;
 
         while end < self.input.len() {
             let curr = self.peek_at(end)?;
-            match curr {
-                " " => break,
-                _ => end += 1,
+            match curr.as_bytes().first() {
+                Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') => end += 1,
+                _ => break,
             }
         }
 
         let delta = end - self.pos;
         let lexeme = self.input.get(self.pos..end).unwrap().to_string();
-        self.push_token(TokenClass::Atom(AtomToken::Identifier), &lexeme);
+        let token_class = match lexeme.as_str() {
+            "let" => TokenClass::Keyword(KeywordToken::Let),
+            "if" => TokenClass::Keyword(KeywordToken::If),
+            "else" => TokenClass::Keyword(KeywordToken::Else),
+            "while" => TokenClass::Keyword(KeywordToken::While),
+            "loop" => TokenClass::Keyword(KeywordToken::Loop),
+            "break" => TokenClass::Keyword(KeywordToken::Break),
+            "continue" => TokenClass::Keyword(KeywordToken::Continue),
+            "true" | "false" => TokenClass::Atom(AtomToken::BoolLit),
+            "print" => TokenClass::Keyword(KeywordToken::Print),
+            "fun" => TokenClass::Keyword(KeywordToken::Fun),
+            "and" => TokenClass::Op(OpToken::And),
+            "or" => TokenClass::Op(OpToken::Or),
+            _ => TokenClass::Atom(AtomToken::Identifier),
+        };
+        self.push_token(token_class, &lexeme);
         self.advance(delta);
 
         Ok(())
@@ -233,10 +350,19 @@ impl Lexer {
             match lexeme {
                 " " => self.advance(1),
                 "\n" => self.new_line(),
-                ";" => self.scan_delimiter(lexeme.to_string())?,
-                "(" | ")" => self.scan_op(lexeme.to_string())?,
+                ";" | "," => self.scan_delimiter(lexeme.to_string())?,
+                "(" | ")" | "{" | "}" => self.scan_op(lexeme.to_string())?,
+                "=" | "!" | "<" | ">" => self.scan_cmp()?,
+                "\"" => self.scan_string()?,
                 "+" | "-" | "/" | "*" => self.scan_binary_op(lexeme.to_string())?,
                 "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => self.scan_num_lit()?,
+                _ if matches!(
+                    lexeme.as_bytes().first(),
+                    Some(b'a'..=b'z' | b'A'..=b'Z' | b'_')
+                ) =>
+                {
+                    self.scan_word()?
+                }
                 _ => return Err(self.create_unexpected_char_err(lexeme)),
             }
         }
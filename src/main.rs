@@ -1,11 +1,18 @@
+use crate::compiler::Compiler;
+use crate::interpreter::{Inspect, Interpreter};
 use crate::repl::{Evaluator, EvaluatorOk, Repl};
 
+mod compiler;
+mod diagnostics;
+mod interpreter;
+mod lexer;
+mod parser;
 mod repl;
 
 struct Printer {}
 
 impl Evaluator for Printer {
-    fn eval(&mut self, input: &str) -> Result<EvaluatorOk, String> {
+    fn eval(&mut self, input: String) -> Result<EvaluatorOk, String> {
         if input.is_empty() {
             return Err(String::from("empty string"));
         };
@@ -18,7 +25,71 @@ impl Evaluator for Printer {
     }
 }
 
+/// Compiles each input and echoes the resulting bytecode disassembly, giving a
+/// "show bytecode" REPL for debugging what `compile` emits.
+struct Disassembler {
+    compiler: Compiler,
+}
+
+impl Evaluator for Disassembler {
+    fn eval(&mut self, input: String) -> Result<EvaluatorOk, String> {
+        if input.is_empty() {
+            return Err(String::from("empty string"));
+        };
+        match self.compiler.compile(input) {
+            Ok(chunk) => Ok(EvaluatorOk::Append(chunk.disassemble())),
+            Err(err) => Err(format!("{err:?}")),
+        }
+    }
+}
+
+/// Inspects a whole file at the given pipeline stage, e.g.
+/// `rlox --emit=bytecode program.lox`.
+fn run_emit(stage: &str, path: Option<&str>) {
+    let stage = match Inspect::from_flag(stage) {
+        Some(stage) => stage,
+        None => {
+            eprintln!("unknown --emit stage `{stage}` (expected tokens|ast|bytecode)");
+            return;
+        }
+    };
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("--emit requires a source file path");
+            return;
+        }
+    };
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("could not read {path}: {err}");
+            return;
+        }
+    };
+    match Interpreter::new().emit(source, stage) {
+        Ok(listing) => println!("{listing}"),
+        Err(rendered) => eprintln!("{rendered}"),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(stage) = args.iter().find_map(|arg| arg.strip_prefix("--emit=")) {
+        let path = args.last().filter(|arg| !arg.starts_with("--emit="));
+        run_emit(stage, path.map(String::as_str));
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--disasm") {
+        let mut disassembler = Disassembler {
+            compiler: Compiler::new(),
+        };
+        let mut repl = Repl::new(&mut disassembler);
+        let _ = repl.start("Bytecode disassembly mode. Type an expression to see its chunk.");
+        return;
+    }
+
     let mut printer = Printer {};
     let mut repl = Repl::new(&mut printer);
     let _ = repl.start("Welcome to my echo printer!");
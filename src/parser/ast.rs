@@ -56,8 +56,60 @@ pub enum AstNode {
         token: Token,
         value: f64,
     },
+    ImaginaryLit {
+        token: Token,
+        value: f64,
+    },
+    BoolLit {
+        token: Token,
+        value: bool,
+    },
+    StringLit {
+        token: Token,
+        value: String,
+    },
+    PrintStmt {
+        token: Token,
+        expression: Box<AstNode>,
+    },
     VariableAccessExpr {
         token: Token,
         identifier: String,
     },
+    Block {
+        token: Token,
+        statements: Vec<Box<AstNode>>,
+    },
+    IfStmt {
+        token: Token,
+        condition: Box<AstNode>,
+        then_branch: Box<AstNode>,
+        else_branch: Option<Box<AstNode>>,
+    },
+    WhileStmt {
+        token: Token,
+        condition: Box<AstNode>,
+        body: Box<AstNode>,
+    },
+    LoopStmt {
+        token: Token,
+        body: Box<AstNode>,
+    },
+    FunctionDecl {
+        token: Token,
+        name: String,
+        params: Vec<String>,
+        body: Box<AstNode>,
+    },
+    Call {
+        token: Token,
+        callee: Box<AstNode>,
+        args: Vec<Box<AstNode>>,
+    },
+    BreakStmt {
+        token: Token,
+    },
+    ContinueStmt {
+        token: Token,
+    },
 }
@@ -11,6 +11,20 @@ fn infix_bp(op: &OpToken) -> Option<(f32, f32)> {
         OpToken::Slash => Some((9.1, 9.0)),
         OpToken::Min => Some((8.1, 11.0)),
         OpToken::Plus => Some((7.1, 7.0)),
+        OpToken::Less | OpToken::LessEq | OpToken::Greater | OpToken::GreaterEq => {
+            Some((4.1, 4.0))
+        }
+        OpToken::EqEq | OpToken::BangEq => Some((3.1, 3.0)),
+        OpToken::And => Some((2.1, 2.0)),
+        OpToken::Or => Some((1.1, 1.0)),
+        _ => None,
+    }
+}
+
+fn prefix_bp(op: &OpToken) -> Option<f32> {
+    match op {
+        OpToken::Min => Some(11.0),
+        OpToken::Bang => Some(12.0),
         _ => None,
     }
 }
@@ -73,6 +87,7 @@ impl<'a> Parser<'a> {
     fn parse_tokens(&mut self) -> ParseResult {
         let token = self.peek()?;
         match token.token_class {
+            TokenClass::Op(OpToken::LeftBrace) | TokenClass::Keyword(_) => self.parse_stmt(),
             TokenClass::Atom(_) | TokenClass::Op(_) => self.parse_expr(0.0),
             _ => self.parse_stmt(),
         }
@@ -80,13 +95,133 @@ impl<'a> Parser<'a> {
 
     // statements
     fn parse_stmt(&mut self) -> ParseResult {
-        let token = self.consume()?;
-        let statement = match token.token_class {
-            TokenClass::Keyword(KeywordToken::Let) => self.parse_variable_assignment_stmt()?,
+        let token = self.peek()?.clone();
+        match token.token_class {
+            TokenClass::Op(OpToken::LeftBrace) => self.parse_block(),
+            TokenClass::Keyword(KeywordToken::If) => self.parse_if_stmt(),
+            TokenClass::Keyword(KeywordToken::While) => self.parse_while_stmt(),
+            TokenClass::Keyword(KeywordToken::Loop) => self.parse_loop_stmt(),
+            TokenClass::Keyword(KeywordToken::Fun) => self.parse_function_decl(),
+            TokenClass::Keyword(KeywordToken::Let) => {
+                self.consume()?;
+                let statement = self.parse_variable_assignment_stmt()?;
+                self.consume_expecting(TokenClass::Delim(DelimToken::Semicolon))?;
+                Ok(statement)
+            }
+            TokenClass::Keyword(KeywordToken::Print) => {
+                let statement = self.parse_print_stmt()?;
+                self.consume_expecting(TokenClass::Delim(DelimToken::Semicolon))?;
+                Ok(statement)
+            }
+            TokenClass::Keyword(KeywordToken::Break) => {
+                let token = self.consume()?;
+                self.consume_expecting(TokenClass::Delim(DelimToken::Semicolon))?;
+                Ok(Box::new(AstNode::BreakStmt { token }))
+            }
+            TokenClass::Keyword(KeywordToken::Continue) => {
+                let token = self.consume()?;
+                self.consume_expecting(TokenClass::Delim(DelimToken::Semicolon))?;
+                Ok(Box::new(AstNode::ContinueStmt { token }))
+            }
             _ => todo!(),
+        }
+    }
+
+    fn parse_function_decl(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Keyword(KeywordToken::Fun))?;
+        let name = self
+            .consume_expecting(TokenClass::Atom(AtomToken::Identifier))?
+            .lexeme;
+        self.consume_expecting(TokenClass::Op(OpToken::LeftParen))?;
+
+        let mut params = Vec::new();
+        if !matches!(
+            self.peek()?.token_class,
+            TokenClass::Op(OpToken::RightParen)
+        ) {
+            loop {
+                let param = self.consume_expecting(TokenClass::Atom(AtomToken::Identifier))?;
+                params.push(param.lexeme);
+                match self.peek()?.token_class {
+                    TokenClass::Delim(DelimToken::Comma) => {
+                        self.consume()?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        self.consume_expecting(TokenClass::Op(OpToken::RightParen))?;
+
+        let body = self.parse_block()?;
+        Ok(Box::new(AstNode::FunctionDecl {
+            token,
+            name,
+            params,
+            body,
+        }))
+    }
+
+    fn parse_print_stmt(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Keyword(KeywordToken::Print))?;
+        self.consume_expecting(TokenClass::Op(OpToken::LeftParen))?;
+        let expression = self.parse_expr(0.0)?;
+        self.consume_expecting(TokenClass::Op(OpToken::RightParen))?;
+        Ok(Box::new(AstNode::PrintStmt { token, expression }))
+    }
+
+    fn parse_block(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Op(OpToken::LeftBrace))?;
+        let mut statements = Vec::new();
+        loop {
+            match &self.peek()?.token_class {
+                TokenClass::Op(OpToken::RightBrace) => break,
+                TokenClass::Delim(DelimToken::Semicolon) => {
+                    self.consume()?;
+                }
+                TokenClass::Delim(DelimToken::EoF) => {
+                    return Err(ParserError::UnclosedExpression { token });
+                }
+                _ => statements.push(self.parse_tokens()?),
+            }
+        }
+        self.consume_expecting(TokenClass::Op(OpToken::RightBrace))?;
+        Ok(Box::new(AstNode::Block { token, statements }))
+    }
+
+    fn parse_if_stmt(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Keyword(KeywordToken::If))?;
+        let condition = self.parse_expr(0.0)?;
+        let then_branch = self.parse_block()?;
+        let else_branch = match &self.peek()?.token_class {
+            TokenClass::Keyword(KeywordToken::Else) => {
+                self.consume()?;
+                Some(self.parse_block()?)
+            }
+            _ => None,
         };
-        self.consume_expecting(TokenClass::Delim(DelimToken::Semicolon))?;
-        Ok(statement)
+        Ok(Box::new(AstNode::IfStmt {
+            token,
+            condition,
+            then_branch,
+            else_branch,
+        }))
+    }
+
+    fn parse_while_stmt(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Keyword(KeywordToken::While))?;
+        let condition = self.parse_expr(0.0)?;
+        let body = self.parse_block()?;
+        Ok(Box::new(AstNode::WhileStmt {
+            token,
+            condition,
+            body,
+        }))
+    }
+
+    fn parse_loop_stmt(&mut self) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Keyword(KeywordToken::Loop))?;
+        let body = self.parse_block()?;
+        Ok(Box::new(AstNode::LoopStmt { token, body }))
     }
 
     fn parse_variable_assignment_stmt(&mut self) -> ParseResult {
@@ -105,10 +240,13 @@ impl<'a> Parser<'a> {
         let mut lhs = match lhs_token.token_class {
             TokenClass::Atom(ref atom) => match atom {
                 AtomToken::NumericLit => self.parse_numeric_lit(&lhs_token),
+                AtomToken::ImaginaryLit => self.parse_imaginary_lit(&lhs_token),
+                AtomToken::BoolLit => self.parse_bool_lit(&lhs_token),
+                AtomToken::StringLit => self.parse_string_lit(&lhs_token),
                 AtomToken::Identifier => self.parse_identifier(&lhs_token),
             },
             TokenClass::Op(ref op) => match op {
-                OpToken::Min => self.parse_unary_expr(&lhs_token),
+                OpToken::Min | OpToken::Bang => self.parse_unary_expr(&lhs_token),
                 OpToken::LeftParen => self.parse_nested_expr(&lhs_token),
                 _ => {
                     return Err(ParserError::UnexpectedUnaryOperator {
@@ -125,6 +263,15 @@ impl<'a> Parser<'a> {
         }?;
 
         loop {
+            // a `(` immediately following an operand is a call, not a grouping
+            if matches!(
+                self.peek()?.token_class,
+                TokenClass::Op(OpToken::LeftParen)
+            ) {
+                lhs = self.parse_call(lhs)?;
+                continue;
+            }
+
             let op_token = self.peek()?;
             let infix_op = match &op_token.token_class {
                 TokenClass::Delim(delim)
@@ -168,6 +315,27 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_imaginary_lit(&self, token: &Token) -> ParseResult {
+        Ok(Box::new(AstNode::ImaginaryLit {
+            token: token.clone(),
+            value: token.lexeme.parse().unwrap(),
+        }))
+    }
+
+    fn parse_bool_lit(&self, token: &Token) -> ParseResult {
+        Ok(Box::new(AstNode::BoolLit {
+            token: token.clone(),
+            value: token.lexeme == "true",
+        }))
+    }
+
+    fn parse_string_lit(&self, token: &Token) -> ParseResult {
+        Ok(Box::new(AstNode::StringLit {
+            token: token.clone(),
+            value: token.lexeme.clone(),
+        }))
+    }
+
     fn parse_identifier(&self, token: &Token) -> ParseResult {
         Ok(Box::new(AstNode::VariableAccessExpr {
             token: token.clone(),
@@ -185,7 +353,7 @@ impl<'a> Parser<'a> {
             }
         };
 
-        let (_, right_bp) = infix_bp(op).unwrap();
+        let right_bp = prefix_bp(op).unwrap();
         let operand = self.parse_expr(right_bp)?;
         Ok(Box::new(AstNode::UnaryExpr {
             token: token.clone(),
@@ -193,6 +361,31 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_call(&mut self, callee: Box<AstNode>) -> ParseResult {
+        let token = self.consume_expecting(TokenClass::Op(OpToken::LeftParen))?;
+        let mut args = Vec::new();
+        if !matches!(
+            self.peek()?.token_class,
+            TokenClass::Op(OpToken::RightParen)
+        ) {
+            loop {
+                args.push(self.parse_expr(0.0)?);
+                match self.peek()?.token_class {
+                    TokenClass::Delim(DelimToken::Comma) => {
+                        self.consume()?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        self.consume_expecting(TokenClass::Op(OpToken::RightParen))?;
+        Ok(Box::new(AstNode::Call {
+            token,
+            callee,
+            args,
+        }))
+    }
+
     fn parse_nested_expr(&mut self, token: &Token) -> ParseResult {
         let nested_expression = self.parse_expr(0.0)?;
         let expression_end_token = self.consume()?;